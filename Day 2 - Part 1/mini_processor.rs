@@ -1,68 +1,186 @@
-fn add(ram: &mut [i64], counter_position: u64) {
-    let input_1_position = ram[(counter_position + 1) as usize];
-    let input_2_position = ram[(counter_position + 2) as usize];
-    let output_position = ram[(counter_position + 3) as usize];
+use std::collections::VecDeque;
 
-    ram[output_position as usize] = ram[input_1_position as usize] + ram[input_2_position as usize];
+enum VmStatus {
+    WaitingForInput,
+    ProducedOutput(i64),
+    Halted,
 }
 
-fn multiply(ram: &mut [i64], counter_position: u64) {
-    let input_1_position = ram[(counter_position + 1) as usize];
-    let input_2_position = ram[(counter_position + 2) as usize];
-    let output_position = ram[(counter_position + 3) as usize];
-
-    ram[output_position as usize] = ram[input_1_position as usize] * ram[input_2_position as usize];
+struct IntcodeVm {
+    ram: Vec<i64>,
+    ip: usize,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
 }
 
-fn print(ram: &mut [i64]) {
-    let mut ram_as_string = String::from("[");
-    for i in 0..ram.len() {
-        ram_as_string.push_str(&ram[i as usize].to_string());
-        if i < (ram.len() - 1) {
-            ram_as_string.push_str(", ");
+impl IntcodeVm {
+    fn new(ram: Vec<i64>) -> IntcodeVm {
+        IntcodeVm {
+            ram,
+            ip: 0,
+            inputs: VecDeque::new(),
+            outputs: Vec::new(),
         }
     }
-    ram_as_string.push_str("]");
 
-    println!("{}", ram_as_string);
-}
+    fn push_input(&mut self, value: i64) {
+        self.inputs.push_back(value);
+    }
 
-fn run(ram: &mut [i64]) {
-    let mut instruction_counter: u64 = 0;
-    let mut num_of_instructions: u64 = 0;
+    // Reads the parameter at `ip + offset`, honoring its mode (0 = position, 1 = immediate).
+    fn read_param(&self, offset: usize, mode: i64) -> i64 {
+        let value = self.ram[self.ip + offset];
 
-    loop {
-        let op_code = ram[instruction_counter as usize];
+        match mode {
+            0 => self.ram[value as usize],
+            1 => value,
+            _ => panic!("Unknown parameter mode '{}'.", mode),
+        }
+    }
+
+    // Destination parameters are always addresses, so they ignore the mode entirely.
+    fn write_address(&self, offset: usize) -> usize {
+        self.ram[self.ip + offset] as usize
+    }
 
-        match op_code {
-            1 => {
-                add(ram, instruction_counter);
+    // Executes opcodes until the program halts, produces an output, or needs input that
+    // isn't available yet, so callers can wire several VMs together and round-robin them.
+    fn run_until_blocked(&mut self) -> VmStatus {
+        loop {
+            let instruction = self.ram[self.ip];
+            let op_code = instruction % 100;
+            let mode_1 = (instruction / 100) % 10;
+            let mode_2 = (instruction / 1000) % 10;
 
-                instruction_counter += 4;
-                num_of_instructions += 1;
+            match op_code {
+                1 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = self.read_param(1, mode_1) + self.read_param(2, mode_2);
+                    self.ip += 4;
+                }
+                2 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = self.read_param(1, mode_1) * self.read_param(2, mode_2);
+                    self.ip += 4;
+                }
+                3 => {
+                    let input = match self.inputs.pop_front() {
+                        Some(input) => input,
+                        None => return VmStatus::WaitingForInput,
+                    };
+                    let destination = self.write_address(1);
+                    self.ram[destination] = input;
+                    self.ip += 2;
+                }
+                4 => {
+                    let output = self.read_param(1, mode_1);
+                    self.ip += 2;
+                    return VmStatus::ProducedOutput(output);
+                }
+                5 => {
+                    if self.read_param(1, mode_1) != 0 {
+                        self.ip = self.read_param(2, mode_2) as usize;
+                    } else {
+                        self.ip += 3;
+                    }
+                }
+                6 => {
+                    if self.read_param(1, mode_1) == 0 {
+                        self.ip = self.read_param(2, mode_2) as usize;
+                    } else {
+                        self.ip += 3;
+                    }
+                }
+                7 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = if self.read_param(1, mode_1) < self.read_param(2, mode_2) { 1 } else { 0 };
+                    self.ip += 4;
+                }
+                8 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = if self.read_param(1, mode_1) == self.read_param(2, mode_2) { 1 } else { 0 };
+                    self.ip += 4;
+                }
+                99 => return VmStatus::Halted,
+                _ => {
+                    panic!("Crash due to unknown opcode '{}' at position {}.", op_code, self.ip);
+                }
             }
-            2 => {
-                multiply(ram, instruction_counter);
+        }
+    }
 
-                instruction_counter += 4;
-                num_of_instructions += 1;
+    fn run(&mut self) {
+        loop {
+            match self.run_until_blocked() {
+                VmStatus::ProducedOutput(value) => self.outputs.push(value),
+                VmStatus::WaitingForInput => panic!("No input available."),
+                VmStatus::Halted => break,
             }
-            99 => {
-                //instruction_counter += 1;
-                num_of_instructions += 1;
+        }
+    }
+}
 
-                print(ram);
-                println!("Terminated normally. Executed {} instructions.", num_of_instructions);
-                break
+// Wires the given VMs into a ring, feeds each one its phase setting, then drives opcode 4's
+// output into the next amplifier's input until every amplifier has halted, returning the last
+// signal the final amplifier produced.
+fn run_amplifier_feedback_loop(mut amplifiers: Vec<IntcodeVm>, phase_settings: &[i64]) -> i64 {
+    let num_of_amplifiers = amplifiers.len();
+
+    for (amplifier, &phase_setting) in amplifiers.iter_mut().zip(phase_settings.iter()) {
+        amplifier.push_input(phase_setting);
+    }
+    amplifiers[0].push_input(0);
+
+    let mut halted = vec![false; num_of_amplifiers];
+    let mut last_output = 0;
+    let mut current = 0;
+
+    while halted.iter().any(|&is_halted| !is_halted) {
+        if halted[current] {
+            current = (current + 1) % num_of_amplifiers;
+            continue;
+        }
+
+        match amplifiers[current].run_until_blocked() {
+            VmStatus::ProducedOutput(value) => {
+                if current == num_of_amplifiers - 1 {
+                    last_output = value;
+                }
+                let next = (current + 1) % num_of_amplifiers;
+                amplifiers[next].push_input(value);
+            }
+            VmStatus::Halted => {
+                halted[current] = true;
             }
-            _ => {
-                panic!("Crash due to unknown opcode '{}' at position {}.", op_code, instruction_counter);
+            VmStatus::WaitingForInput => {
+                panic!("Amplifier {} is waiting for input that was never provided.", current);
             }
         }
+        current = (current + 1) % num_of_amplifiers;
+    }
+
+    last_output
+}
+
+fn print(ram: &[i64]) {
+    let mut ram_as_string = String::from("[");
+    for i in 0..ram.len() {
+        ram_as_string.push_str(&ram[i as usize].to_string());
+        if i < (ram.len() - 1) {
+            ram_as_string.push_str(", ");
+        }
     }
+    ram_as_string.push_str("]");
+
+    println!("{}", ram_as_string);
 }
 
 fn main() {
-    let mut ram: Vec<i64> = vec![1,12,2,3,1,1,2,3,1,3,4,3,1,5,0,3,2,6,1,19,2,19,9,23,1,23,5,27,2,6,27,31,1,31,5,35,1,35,5,39,2,39,6,43,2,43,10,47,1,47,6,51,1,51,6,55,2,55,6,59,1,10,59,63,1,5,63,67,2,10,67,71,1,6,71,75,1,5,75,79,1,10,79,83,2,83,10,87,1,87,9,91,1,91,10,95,2,6,95,99,1,5,99,103,1,103,13,107,1,107,10,111,2,9,111,115,1,115,6,119,2,13,119,123,1,123,6,127,1,5,127,131,2,6,131,135,2,6,135,139,1,139,5,143,1,143,10,147,1,147,2,151,1,151,13,0,99,2,0,14,0];
-    run(&mut ram);
+    let ram: Vec<i64> = vec![1,12,2,3,1,1,2,3,1,3,4,3,1,5,0,3,2,6,1,19,2,19,9,23,1,23,5,27,2,6,27,31,1,31,5,35,1,35,5,39,2,39,6,43,2,43,10,47,1,47,6,51,1,51,6,55,2,55,6,59,1,10,59,63,1,5,63,67,2,10,67,71,1,6,71,75,1,5,75,79,1,10,79,83,2,83,10,87,1,87,9,91,1,91,10,95,2,6,95,99,1,5,99,103,1,103,13,107,1,107,10,111,2,9,111,115,1,115,6,119,2,13,119,123,1,123,6,127,1,5,127,131,2,6,131,135,2,6,135,139,1,139,5,143,1,143,10,147,1,147,2,151,1,151,13,0,99,2,0,14,0];
+    let mut vm = IntcodeVm::new(ram);
+
+    vm.run();
+
+    print(&vm.ram);
+    println!("Terminated normally.");
 }
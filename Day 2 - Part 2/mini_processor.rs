@@ -1,41 +1,94 @@
-fn add(ram: &mut [i64], counter_position: u64) {
-    let input_1_position = ram[(counter_position + 1) as usize];
-    let input_2_position = ram[(counter_position + 2) as usize];
-    let output_position = ram[(counter_position + 3) as usize];
+use std::collections::VecDeque;
 
-    ram[output_position as usize] = ram[input_1_position as usize] + ram[input_2_position as usize];
+struct IntcodeVm {
+    ram: Vec<i64>,
+    ip: usize,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
 }
 
-fn multiply(ram: &mut [i64], counter_position: u64) {
-    let input_1_position = ram[(counter_position + 1) as usize];
-    let input_2_position = ram[(counter_position + 2) as usize];
-    let output_position = ram[(counter_position + 3) as usize];
-
-    ram[output_position as usize] = ram[input_1_position as usize] * ram[input_2_position as usize];
-}
+impl IntcodeVm {
+    fn new(ram: Vec<i64>) -> IntcodeVm {
+        IntcodeVm {
+            ram,
+            ip: 0,
+            inputs: VecDeque::new(),
+            outputs: Vec::new(),
+        }
+    }
 
-fn run(ram: &mut [i64]) {
-    let mut instruction_counter: u64 = 0;
+    // Reads the parameter at `ip + offset`, honoring its mode (0 = position, 1 = immediate).
+    fn read_param(&self, offset: usize, mode: i64) -> i64 {
+        let value = self.ram[self.ip + offset];
 
-    loop {
-        let op_code = ram[instruction_counter as usize];
+        match mode {
+            0 => self.ram[value as usize],
+            1 => value,
+            _ => panic!("Unknown parameter mode '{}'.", mode),
+        }
+    }
 
-        match op_code{
-            1 => {
-                add(ram, instruction_counter);
+    // Destination parameters are always addresses, so they ignore the mode entirely.
+    fn write_address(&self, offset: usize) -> usize {
+        self.ram[self.ip + offset] as usize
+    }
 
-                instruction_counter += 4;
-            }
-            2 => {
-                multiply(ram, instruction_counter);
+    fn run(&mut self) {
+        loop {
+            let instruction = self.ram[self.ip];
+            let op_code = instruction % 100;
+            let mode_1 = (instruction / 100) % 10;
+            let mode_2 = (instruction / 1000) % 10;
 
-                instruction_counter += 4;
-            }
-            99 => {
-                break
-            }
-            _ => {
-                panic!("Crash due to unknown opcode '{}' at position {}.", op_code, instruction_counter);
+            match op_code {
+                1 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = self.read_param(1, mode_1) + self.read_param(2, mode_2);
+                    self.ip += 4;
+                }
+                2 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = self.read_param(1, mode_1) * self.read_param(2, mode_2);
+                    self.ip += 4;
+                }
+                3 => {
+                    let destination = self.write_address(1);
+                    let input = self.inputs.pop_front().expect("No input available.");
+                    self.ram[destination] = input;
+                    self.ip += 2;
+                }
+                4 => {
+                    self.outputs.push(self.read_param(1, mode_1));
+                    self.ip += 2;
+                }
+                5 => {
+                    if self.read_param(1, mode_1) != 0 {
+                        self.ip = self.read_param(2, mode_2) as usize;
+                    } else {
+                        self.ip += 3;
+                    }
+                }
+                6 => {
+                    if self.read_param(1, mode_1) == 0 {
+                        self.ip = self.read_param(2, mode_2) as usize;
+                    } else {
+                        self.ip += 3;
+                    }
+                }
+                7 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = if self.read_param(1, mode_1) < self.read_param(2, mode_2) { 1 } else { 0 };
+                    self.ip += 4;
+                }
+                8 => {
+                    let destination = self.write_address(3);
+                    self.ram[destination] = if self.read_param(1, mode_1) == self.read_param(2, mode_2) { 1 } else { 0 };
+                    self.ip += 4;
+                }
+                99 => break,
+                _ => {
+                    panic!("Crash due to unknown opcode '{}' at position {}.", op_code, self.ip);
+                }
             }
         }
     }
@@ -46,12 +99,13 @@ fn main() {
     let output = 19690720;
     for noun in 0..100 {
         for verb in 0..100 {
-            let mut ram  = initial_ram.to_vec();
+            let mut ram = initial_ram.to_vec();
             ram[1] = noun;
             ram[2] = verb;
-            
-            run(&mut ram);
-            if ram[0] == output {
+
+            let mut vm = IntcodeVm::new(ram);
+            vm.run();
+            if vm.ram[0] == output {
                 println!("Found solution: Noun = {} and Verb = {} results in {}.", noun, verb, output);
                 println!("100 * {} + {} = {}", noun, verb, (100 * noun + verb));
             }
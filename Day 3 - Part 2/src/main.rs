@@ -1,5 +1,6 @@
-use std::collections::VecDeque;
 use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::hash::Hash;
 
 enum Direction {
     Left,
@@ -22,9 +23,9 @@ impl ConvertToStep for str {
         if self.len() < 2 {
             panic!("Invalid step description.");
         }
-    
+
         let mut chars = self.chars();
-        
+
         let direction_char = chars.next().unwrap().to_ascii_lowercase();
         let direction = match direction_char {
             'l' => Direction::Left,
@@ -33,160 +34,449 @@ impl ConvertToStep for str {
             'd' => Direction::Down,
              _  => panic!("Unknown direction found: '{}'.", direction_char)
         };
-    
+
         let num_of_cells = chars.as_str().parse::<usize>().unwrap();
-    
+
         Step{ direction, num_of_cells }
     }
 }
 
 #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
 struct Cell {
-    x: usize,
-    y: usize,
+    x: i32,
+    y: i32,
 }
 
-struct Field {
-    cells: VecDeque<VecDeque<u8>>,
-    center: Cell,
-    // A list of marker and their associated maps that save the cost of each visited cell.
-    position_to_num_of_steps_list: Vec<(u8, HashMap<Cell, u64>)>,
+// Maps a signed logical position onto a flat-array index. `offset` is the index of logical
+// position 0, so `map(pos)` is addressable whenever `offset + pos` falls inside `0..size`.
+#[derive(Clone, Copy, Debug)]
+struct Dimension {
+    offset: u32,
+    size: u32,
 }
 
-impl Field {
-    fn new(size: usize, fill_value: u8, center_value: u8) -> Field {
-        if size == 0 {
-            panic!("A field must have a size > 0.");
+impl Dimension {
+    fn new(offset: u32, size: u32) -> Dimension {
+        Dimension { offset, size }
+    }
+
+    fn map(&self, pos: i32) -> Option<usize> {
+        let mapped = self.offset as i32 + pos;
+        if mapped >= 0 && (mapped as u32) < self.size {
+            Some(mapped as usize)
+        } else {
+            None
         }
+    }
 
-        let mut field = Field {
-            cells: vec![vec![fill_value; size].into_iter().collect(); size].into_iter().collect(),
-            center: Cell{ x: 0, y: 0 },
-            position_to_num_of_steps_list: Vec::new(),
-        };
-        field.cells[0][0] = center_value;
-        field
+    // Returns the smallest dimension that still addresses every position the old one did,
+    // plus `pos`.
+    fn include(&self, pos: i32) -> Dimension {
+        let new_offset = std::cmp::max(self.offset as i32, -pos) as u32;
+        let shift = new_offset - self.offset;
+        let new_size = std::cmp::max(self.size + shift, (new_offset as i32 + pos + 1) as u32);
+
+        Dimension { offset: new_offset, size: new_size }
+    }
+
+    // Pads one cell on each side.
+    fn extend(&self) -> Dimension {
+        Dimension { offset: self.offset + 1, size: self.size + 2 }
     }
 }
 
-trait CanGrow {
-    fn grow(&mut self, new_horizontal_size: i64, new_vertical_size: i64);
-    fn grow_horizontally(&mut self, new_horizontal_size: i64);
-    fn grow_vertically(&mut self, new_vertical_size: i64);
+// How far past the requested position a dimension is allowed to grow in one go. Wires (and
+// active regions in cellular automata) tend to keep moving the same way for a while, so padding
+// the growth target keeps a long run of single-cell extensions from triggering a reallocation
+// on every step.
+const GROWTH_PADDING: i32 = 256;
+
+// Widens `dim` just enough to address `pos`, padded in the direction of travel.
+fn grow_dimension(dim: Dimension, pos: i32) -> Dimension {
+    if dim.map(pos).is_some() {
+        return dim;
+    }
+
+    let padded_pos = pos + pos.signum() * GROWTH_PADDING;
+    dim.include(padded_pos)
 }
 
-trait HandleLines {
-    fn add_line(&mut self, line: &Vec<&str>, marker: u8);
-    fn calculate_intersections(&self, marker: u8) -> Vec<Cell>;
-    fn calculate_minimum_steps_to_reach_any_intersection(&self, marker: u8) -> u64;
+// A growable `RANK`-dimensional grid of `u8` cells, backed by a flat `Vec`. The wire field uses
+// it at rank 2; for cellular automata of any rank, `step()` computes the next generation from a
+// birth/survive rule over the full `3^RANK - 1` neighborhood.
+struct Grid<const RANK: usize> {
+    dims: [Dimension; RANK],
+    cells: Vec<u8>,
 }
 
-impl CanGrow for Field {
-    fn grow(&mut self, new_horizontal_size: i64, new_vertical_size: i64) {
-        self.grow_horizontally(new_horizontal_size);
-        self.grow_vertically(new_vertical_size);
+impl<const RANK: usize> Grid<RANK> {
+    fn new(dims: [Dimension; RANK]) -> Grid<RANK> {
+        let len = dims.iter().map(|dim| dim.size as usize).product();
+        Grid { dims, cells: vec![0; len] }
     }
 
-    fn grow_horizontally(&mut self, new_horizontal_size: i64) {
-        let is_positive_growth = new_horizontal_size >= 0;
-        
-        let minimum_grow_size = 
-            if is_positive_growth { new_horizontal_size.abs() + self.center.x as i64 - self.cells.len() as i64}
-            else { new_horizontal_size.abs() - self.center.x as i64};
+    fn strides_for(dims: &[Dimension; RANK]) -> [usize; RANK] {
+        let mut strides = [1usize; RANK];
+        for axis in 1..RANK {
+            strides[axis] = strides[axis - 1] * dims[axis - 1].size as usize;
+        }
+        strides
+    }
 
-        // If the field shall grow, grow at least 100 cells.
-        let relative_grow_size = 
-            if      minimum_grow_size <= 0   { 0 }
-            else if minimum_grow_size <= 100 { 100 as usize }
-            else    { minimum_grow_size as usize };
+    fn strides(&self) -> [usize; RANK] {
+        Grid::<RANK>::strides_for(&self.dims)
+    }
 
-        let current_vertical_size = self.cells[0].len();
+    fn map(&self, coord: [i32; RANK]) -> Option<usize> {
+        let strides = self.strides();
+        let mut index = 0usize;
+        for axis in 0..RANK {
+            index += self.dims[axis].map(coord[axis])? * strides[axis];
+        }
+        Some(index)
+    }
+
+    fn is_active(&self, coord: [i32; RANK]) -> bool {
+        self.map(coord).map(|index| self.cells[index] != 0).unwrap_or(false)
+    }
 
-        if is_positive_growth {
-            for _ in 0..relative_grow_size {
-                self.cells.push_back(VecDeque::with_capacity(current_vertical_size));
-                self.cells.back_mut().unwrap().resize(current_vertical_size, 0);
+    fn fill(&mut self, value: u8) {
+        for cell in self.cells.iter_mut() {
+            *cell = value;
+        }
+    }
+
+    fn set(&mut self, coord: [i32; RANK], value: u8) {
+        let index = self.map(coord).expect("Coordinate out of bounds.");
+        self.cells[index] = value;
+    }
+
+    fn mark(&mut self, coord: [i32; RANK], marker: u8) {
+        let index = self.map(coord).expect("Coordinate out of bounds.");
+        self.cells[index] |= marker;
+    }
+
+    // Reallocates the backing storage for `new_dims`, copying every populated cell across.
+    // Axis 0 is always the fastest-varying one, so its extent is contiguous in `cells`. Copying
+    // whole axis-0 runs (rather than re-deriving every single cell's coordinate) keeps this
+    // linear in the grid's volume instead of linear in volume times `RANK`.
+    fn resize_to(&mut self, new_dims: [Dimension; RANK]) {
+        let old_strides = self.strides();
+        let new_strides = Grid::<RANK>::strides_for(&new_dims);
+        let old_size_0 = self.dims[0].size as usize;
+        let axis_0_shift = new_dims[0].offset as i32 - self.dims[0].offset as i32;
+        let mut new_cells = vec![0u8; new_dims.iter().map(|dim| dim.size as usize).product()];
+
+        let num_rows = if old_size_0 == 0 { 0 } else { self.cells.len() / old_size_0 };
+
+        for row in 0..num_rows {
+            let old_row_start = row * old_size_0;
+
+            let mut new_row_start = 0usize;
+            for axis in 1..RANK {
+                let old_coord = (old_row_start / old_strides[axis]) % self.dims[axis].size as usize;
+                let logical = old_coord as i32 - self.dims[axis].offset as i32;
+                let new_coord = new_dims[axis].map(logical).expect("New dimensions must contain the old grid.");
+                new_row_start += new_coord * new_strides[axis];
             }
+            new_row_start = (new_row_start as i32 + axis_0_shift) as usize;
+
+            let old_row = &self.cells[old_row_start..old_row_start + old_size_0];
+            new_cells[new_row_start..new_row_start + old_size_0].copy_from_slice(old_row);
+        }
+
+        self.dims = new_dims;
+        self.cells = new_cells;
+    }
+
+    // Grows every axis that cannot yet address `coord` so that it can, padded by
+    // `GROWTH_PADDING` via `grow_dimension`.
+    fn grow_to_contain(&mut self, coord: [i32; RANK]) {
+        if self.map(coord).is_some() {
+            return;
+        }
+
+        let mut new_dims = self.dims;
+        for axis in 0..RANK {
+            new_dims[axis] = grow_dimension(new_dims[axis], coord[axis]);
         }
-        else {
-            for _ in 0..relative_grow_size {
-                self.cells.push_front(VecDeque::with_capacity(current_vertical_size));
-                self.cells.front_mut().unwrap().resize(current_vertical_size, 0);
+        self.resize_to(new_dims);
+    }
+
+    // Computes the next generation: auto-extends every axis by one cell, then applies
+    // `rule(is_currently_active, active_neighbor_count)` to every cell in the extended grid.
+    fn step<F: Fn(bool, usize) -> bool>(&self, rule: F) -> Grid<RANK> {
+        let mut extended_dims = self.dims;
+        for axis in 0..RANK {
+            extended_dims[axis] = extended_dims[axis].extend();
+        }
+
+        let mut extended = Grid::new(self.dims);
+        extended.cells.copy_from_slice(&self.cells);
+        extended.resize_to(extended_dims);
+
+        let offsets = neighbor_offsets::<RANK>();
+        let strides = extended.strides();
+        let mut next = Grid::new(extended.dims);
+
+        for index in 0..extended.cells.len() {
+            let mut logical = [0i32; RANK];
+            for axis in 0..RANK {
+                let coord = (index / strides[axis]) % extended.dims[axis].size as usize;
+                logical[axis] = coord as i32 - extended.dims[axis].offset as i32;
             }
+
+            let active_neighbors = offsets.iter()
+                .filter(|offset| {
+                    let mut neighbor = logical;
+                    for axis in 0..RANK {
+                        neighbor[axis] += offset[axis];
+                    }
+                    extended.is_active(neighbor)
+                })
+                .count();
+
+            next.cells[index] = if rule(extended.cells[index] != 0, active_neighbors) { 1 } else { 0 };
         }
 
-        // Move the center if the deque grew to the left.
-        if !is_positive_growth {
-            self.center.x += relative_grow_size as usize;
+        next
+    }
+}
+
+// All offsets in `{-1, 0, 1}^RANK` except the all-zero one, i.e. the full Moore neighborhood.
+fn neighbor_offsets<const RANK: usize>() -> Vec<[i32; RANK]> {
+    let mut offsets = vec![[0i32; RANK]];
+    for axis in 0..RANK {
+        let mut widened = Vec::new();
+        for offset in &offsets {
+            for delta in [-1, 0, 1] {
+                let mut widened_offset = *offset;
+                widened_offset[axis] = delta;
+                widened.push(widened_offset);
+            }
+        }
+        offsets = widened;
+    }
+    offsets.into_iter().filter(|offset| offset.iter().any(|&delta| delta != 0)).collect()
+}
+
+// A simple undirected adjacency-list graph, reusable across puzzles that need shortest-path
+// queries over an explored grid (wires here, mazes/droids later). Edges carry a step weight so a
+// caller tracing a path that revisits vertices (e.g. a wire crossing itself) can still record the
+// true number of steps between two directly-connected vertices, not just a hop count.
+struct Graph<T: Eq + Hash + Clone> {
+    adjacency: HashMap<T, Vec<(T, u64)>>,
+}
+
+impl<T: Eq + Hash + Clone> Graph<T> {
+    fn new() -> Graph<T> {
+        Graph { adjacency: HashMap::new() }
+    }
+
+    fn add_vertex(&mut self, vertex: T) {
+        self.adjacency.entry(vertex).or_insert_with(Vec::new);
+    }
+
+    fn contains(&self, vertex: &T) -> bool {
+        self.adjacency.contains_key(vertex)
+    }
+
+    fn add_edge(&mut self, a: T, b: T, weight: u64) {
+        self.add_vertex(a.clone());
+        self.add_vertex(b.clone());
+        self.adjacency.get_mut(&a).unwrap().push((b.clone(), weight));
+        self.adjacency.get_mut(&b).unwrap().push((a, weight));
+    }
+
+    fn neighbors(&self, vertex: &T) -> &[(T, u64)] {
+        self.adjacency.get(vertex).map(|edges| edges.as_slice()).unwrap_or(&[])
+    }
+
+    // Shortest step count from `start` to every vertex reachable from it. A plain FIFO walk (no
+    // priority queue) is enough because the graphs we build here are trees, so there is never a
+    // cheaper alternate route to re-relax.
+    fn bfs(&self, start: T) -> HashMap<T, u64> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        distances.insert(start.clone(), 0);
+        queue.push_back(start);
+
+        while let Some(current) = queue.pop_front() {
+            let current_distance = distances[&current];
+            for (neighbor, weight) in self.neighbors(&current) {
+                if !distances.contains_key(neighbor) {
+                    distances.insert(neighbor.clone(), current_distance + weight);
+                    queue.push_back(neighbor.clone());
+                }
+            }
         }
+
+        distances
+    }
+
+    fn shortest_path(&self, start: T, goal: &T) -> Option<u64> {
+        self.bfs(start).get(goal).copied()
     }
-    
-    fn grow_vertically(&mut self, new_vertical_size: i64) {
-        let is_positive_growth = new_vertical_size >= 0;
 
-        let minimum_grow_size = 
-            if is_positive_growth { new_vertical_size.abs() + self.center.y as i64 - self.cells[0].len() as i64}
-            else { new_vertical_size.abs() - self.center.y as i64 };
+    fn reachable(&self, start: T) -> Vec<T> {
+        self.bfs(start).keys().cloned().collect()
+    }
+}
 
-        // If the field shall grow, grow at least 100 cells.
-        let relative_grow_size = 
-            if      minimum_grow_size <= 0   { 0 }
-            else if minimum_grow_size <= 100 { 100 as usize }
-            else    { minimum_grow_size as usize };
+// A 2-D range-minimum sparse table. `table[k][l][i][j]` holds the minimum over the rectangle
+// anchored at `(i, j)` spanning `2^k` rows and `2^l` columns, so any rectangular query can be
+// answered in O(1) (as the min of four overlapping precomputed blocks) after one O(HW log H log W)
+// build.
+struct SparseTable {
+    table: Vec<Vec<Vec<Vec<u64>>>>,
+    height: usize,
+    width: usize,
+}
 
-        let current_vertical_size = self.cells[0].len();
+impl SparseTable {
+    fn build(values: &[Vec<u64>]) -> SparseTable {
+        let height = values.len();
+        let width = if height == 0 { 0 } else { values[0].len() };
+        let max_k = log2_levels(height);
+        let max_l = log2_levels(width);
+
+        let mut table = vec![vec![vec![vec![u64::MAX; width]; height]; max_l]; max_k];
+        for i in 0..height {
+            for j in 0..width {
+                table[0][0][i][j] = values[i][j];
+            }
+        }
 
-        if is_positive_growth {
-            for vertical_vec in self.cells.iter_mut() {
-                vertical_vec.resize(current_vertical_size + relative_grow_size, 0);
+        // Double the row span first, at column span 1...
+        for k in 1..max_k {
+            let span = 1usize << (k - 1);
+            for i in 0..height {
+                for j in 0..width {
+                    let lower = table[k - 1][0][i][j];
+                    let upper = if i + span < height { table[k - 1][0][i + span][j] } else { u64::MAX };
+                    table[k][0][i][j] = lower.min(upper);
+                }
             }
         }
-        else {
-            for vertical_vec in self.cells.iter_mut() {
-                for _ in 0..relative_grow_size {
-                    vertical_vec.push_front(0);
+        // ...then double the column span for every row span.
+        for k in 0..max_k {
+            for l in 1..max_l {
+                let span = 1usize << (l - 1);
+                for i in 0..height {
+                    for j in 0..width {
+                        let left = table[k][l - 1][i][j];
+                        let right = if j + span < width { table[k][l - 1][i][j + span] } else { u64::MAX };
+                        table[k][l][i][j] = left.min(right);
+                    }
                 }
             }
         }
 
-        // Move the center if the deque grew to the top.
-        if !is_positive_growth {
-            self.center.y += relative_grow_size as usize;
+        SparseTable { table, height, width }
+    }
+
+    fn min_in_rect(&self, i1: usize, j1: usize, i2: usize, j2: usize) -> u64 {
+        if i1 > i2 || j1 > j2 || i2 >= self.height || j2 >= self.width {
+            panic!("Rectangle ({}, {})..=({}, {}) is out of bounds for a {}x{} table.", i1, j1, i2, j2, self.height, self.width);
+        }
+
+        let k = log2_floor(i2 - i1 + 1);
+        let l = log2_floor(j2 - j1 + 1);
+        let row_span = 1usize << k;
+        let col_span = 1usize << l;
+
+        let top_left = self.table[k][l][i1][j1];
+        let top_right = self.table[k][l][i1][j2 + 1 - col_span];
+        let bottom_left = self.table[k][l][i2 + 1 - row_span][j1];
+        let bottom_right = self.table[k][l][i2 + 1 - row_span][j2 + 1 - col_span];
+
+        top_left.min(top_right).min(bottom_left).min(bottom_right)
+    }
+}
+
+// Number of doubling levels needed to cover `size`, i.e. `floor(log2(size)) + 1` (0 for an empty axis).
+fn log2_levels(size: usize) -> usize {
+    if size == 0 { 0 } else { log2_floor(size) + 1 }
+}
+
+fn log2_floor(value: usize) -> usize {
+    (usize::BITS - 1 - value.leading_zeros()) as usize
+}
+
+// Intersections are sparse compared to the field's full coordinate range (a few dozen points
+// scattered across a span of thousands of cells), so the sparse table is built over the distinct
+// x/y coordinates that intersections actually use rather than the whole field, which would blow up
+// an O(HW logH logW) table to gigabytes of mostly-u64::MAX cells.
+struct IntersectionTable {
+    xs: Vec<i32>,
+    ys: Vec<i32>,
+    table: SparseTable,
+}
+
+// Returns the inclusive range of indices into `sorted` (ascending, deduplicated) that fall within
+// `[lo, hi]`, or `None` if none do.
+fn compressed_range(sorted: &[i32], lo: i32, hi: i32) -> Option<(usize, usize)> {
+    let start = sorted.partition_point(|&value| value < lo);
+    let end = sorted.partition_point(|&value| value <= hi);
+    if start < end {
+        Some((start, end - 1))
+    } else {
+        None
+    }
+}
+
+struct Field {
+    grid: Grid<2>,
+    // A list of markers and the per-line graph of visited cells, used for shortest-path queries.
+    line_graphs: Vec<(u8, Graph<Cell>)>,
+    // Cached sparse table of combined step costs at intersections, built on demand.
+    intersection_table: Option<IntersectionTable>,
+}
+
+impl Field {
+    fn new(size: usize, fill_value: u8, center_value: u8) -> Field {
+        if size == 0 {
+            panic!("A field must have a size > 0.");
+        }
+
+        let mut grid = Grid::new([Dimension::new(0, size as u32), Dimension::new(0, size as u32)]);
+        grid.fill(fill_value);
+        grid.set([0, 0], center_value);
+
+        Field {
+            grid,
+            line_graphs: Vec::new(),
+            intersection_table: None,
         }
     }
 }
 
+trait HandleLines {
+    fn add_line(&mut self, line: &Vec<&str>, marker: u8);
+    fn calculate_intersections(&self, marker: u8) -> Vec<Cell>;
+    fn calculate_minimum_steps_to_reach_any_intersection(&self, marker: u8) -> u64;
+}
+
 impl HandleLines for Field {
     fn add_line(&mut self, line: &Vec<&str>, marker: u8) {
         // Check if the marker has already been added.
-        if self.position_to_num_of_steps_list.iter().any(|marker_and_hash_map| marker_and_hash_map.0 == marker) {
+        if self.line_graphs.iter().any(|marker_and_graph| marker_and_graph.0 == marker) {
             panic!("A line with the marker {} has already been added. No marker can be added twice.", marker);
         }
 
-        let mut x: i64 = 0;
-        let mut y: i64 = 0;
-        let mut position_to_num_of_steps: HashMap<Cell, u64> = HashMap::new();
-        let mut taken_steps: u64 = 0;
-
-        //self.position_to_num_of_steps_list.push((marker, HashMap::new()));
-
-        let get_cell = |x, y, center_x, center_y| -> Cell {
-            Cell{ 
-                x: (center_x as i64 + x) as usize, 
-                y: (center_y as i64 + y) as usize 
-            }
-        };
+        let mut x: i32 = 0;
+        let mut y: i32 = 0;
+        let mut graph: Graph<Cell> = Graph::new();
+        let origin = Cell { x, y };
+        graph.add_vertex(origin);
+        // The vertex a new edge would originate from, and how many real steps the wire has
+        // taken since it last stood on a genuinely new (not yet visited) cell.
+        let mut last_new_vertex = origin;
+        let mut steps_since_last_new_vertex: u64 = 0;
 
         for &step_string in line.iter() {
             let step = step_string.to_step();
 
-            match step.direction {
-                Direction::Left  => self.grow_horizontally(x - step.num_of_cells as i64 - 1),
-                Direction::Right => self.grow_horizontally(x + step.num_of_cells as i64 + 1),
-                Direction::Down  => self.grow_vertically(y - step.num_of_cells as i64 - 1),
-                Direction::Up    => self.grow_vertically(y + step.num_of_cells as i64 + 1),
-            };
-
             for _ in 0..step.num_of_cells {
                 match step.direction {
                     Direction::Left  => x -= 1,
@@ -194,31 +484,40 @@ impl HandleLines for Field {
                     Direction::Down  => y -= 1,
                     Direction::Up    => y += 1,
                 };
-                taken_steps +=1;
 
-                let next_cell = get_cell(x, y, self.center.x, self.center.y);
+                self.grid.grow_to_contain([x, y]);
+                self.grid.mark([x, y], marker);
 
-                if self.cells[next_cell.x][next_cell.y] != 0 {
-                    println!("Key: x={}, y={} | Val: {}", next_cell.x, next_cell.y, taken_steps);
+                let next_cell = Cell { x, y };
+                steps_since_last_new_vertex += 1;
+
+                // Wires can cross their own path. Only wire in an edge the first time a cell is
+                // reached, weighted by the real steps elapsed since the last new cell, so the
+                // graph stays a tree and `bfs` distances match the steps actually taken instead of
+                // a shortcut through a later self-intersection.
+                if !graph.contains(&next_cell) {
+                    graph.add_edge(last_new_vertex, next_cell, steps_since_last_new_vertex);
+                    last_new_vertex = next_cell;
+                    steps_since_last_new_vertex = 0;
                 }
-                
-                // Mark cell.
-                self.cells[next_cell.x][next_cell.y] |= marker;
-                // Save cost to get to the current position.
-                position_to_num_of_steps.entry(next_cell).or_insert(taken_steps);
             }
         }
 
-        self.position_to_num_of_steps_list.push((marker, position_to_num_of_steps));
+        self.line_graphs.push((marker, graph));
     }
 
     fn calculate_intersections(&self, marker: u8) -> Vec<Cell> {
         let mut intersections = Vec::new();
-        for x in 0..self.cells.len() {
-            for y in 0..self.cells[0].len() {
-                if self.cells[x][y] == marker {
-                    intersections.push(Cell{x, y});
-                }
+        let strides = self.grid.strides();
+
+        for index in 0..self.grid.cells.len() {
+            if self.grid.cells[index] == marker {
+                let x = (index / strides[0]) % self.grid.dims[0].size as usize;
+                let y = (index / strides[1]) % self.grid.dims[1].size as usize;
+                intersections.push(Cell {
+                    x: x as i32 - self.grid.dims[0].offset as i32,
+                    y: y as i32 - self.grid.dims[1].offset as i32,
+                });
             }
         }
         intersections
@@ -227,48 +526,90 @@ impl HandleLines for Field {
     fn calculate_minimum_steps_to_reach_any_intersection(&self, marker: u8) -> u64 {
         let mut accumulated_marker_for_all_lines: u8 = 0;
 
-        for (line_marker, _) in self.position_to_num_of_steps_list.iter() {
+        for (line_marker, _) in self.line_graphs.iter() {
             accumulated_marker_for_all_lines |= line_marker;
         }
         if accumulated_marker_for_all_lines != marker {
             panic!("The given marker {} must be the sum of the markers of all lines (excepted {}).", marker, accumulated_marker_for_all_lines);
         }
 
-        for (_, map) in self.position_to_num_of_steps_list.iter() {
-            // for (&key, &value) in map.iter() {
-            //     println!("Key: x={}, y={}, val={}", key.x, key.y, value);
-            // }
-            panic!("Waaaay");
-        }
-
-
+        let origin = Cell { x: 0, y: 0 };
         let mut steps_to_reach_intersections: Vec<u64> = Vec::new();
 
         let intersections = self.calculate_intersections(marker);
         for intersection in intersections.iter() {
             let mut taken_steps: u64 = 0;
 
-            for (_, position_to_num_of_steps) in self.position_to_num_of_steps_list.iter() {
-                if position_to_num_of_steps.get(intersection) == None {
-                    println!("Did not find x = {}, y = {}", intersection.x, intersection.y);
-                    continue;
+            for (_, graph) in self.line_graphs.iter() {
+                if let Some(steps) = graph.shortest_path(origin, intersection) {
+                    taken_steps += steps;
                 }
-
-                taken_steps += position_to_num_of_steps.get(intersection).unwrap();
             }
 
             steps_to_reach_intersections.push(taken_steps);
         }
-        println!("XXX: intersections: {}, steps_to_reach_intersections: {}", intersections.len(), steps_to_reach_intersections.len());
+
         steps_to_reach_intersections.sort();
         *steps_to_reach_intersections.first().unwrap()
     }
 }
 
+trait QueryIntersectionCosts {
+    fn build_intersection_table(&mut self, marker: u8);
+    fn min_in_rect(&self, x1: i32, y1: i32, x2: i32, y2: i32) -> u64;
+}
+
+impl QueryIntersectionCosts for Field {
+    // Builds a grid of combined step costs (u64::MAX everywhere but an intersection), indexed by
+    // the distinct x/y coordinates intersections actually use, and turns it into a sparse table so
+    // repeated `min_in_rect` queries no longer need to rescan the field.
+    fn build_intersection_table(&mut self, marker: u8) {
+        let intersections = self.calculate_intersections(marker);
+        let origin = Cell { x: 0, y: 0 };
+
+        let mut xs: Vec<i32> = intersections.iter().map(|cell| cell.x).collect();
+        xs.sort();
+        xs.dedup();
+        let mut ys: Vec<i32> = intersections.iter().map(|cell| cell.y).collect();
+        ys.sort();
+        ys.dedup();
+
+        let mut values = vec![vec![u64::MAX; ys.len()]; xs.len()];
+        for cell in intersections.iter() {
+            let i = xs.binary_search(&cell.x).unwrap();
+            let j = ys.binary_search(&cell.y).unwrap();
+
+            let mut combined_steps: u64 = 0;
+            for (_, graph) in self.line_graphs.iter() {
+                if let Some(steps) = graph.shortest_path(origin, cell) {
+                    combined_steps += steps;
+                }
+            }
+            values[i][j] = combined_steps;
+        }
+
+        self.intersection_table = Some(IntersectionTable { xs, ys, table: SparseTable::build(&values) });
+    }
+
+    fn min_in_rect(&self, x1: i32, y1: i32, x2: i32, y2: i32) -> u64 {
+        let intersection_table = self.intersection_table.as_ref()
+            .expect("The intersection table must be built via build_intersection_table before querying it.");
+
+        let i_range = compressed_range(&intersection_table.xs, x1, x2);
+        let j_range = compressed_range(&intersection_table.ys, y1, y2);
+
+        match (i_range, j_range) {
+            (Some((i1, i2)), Some((j1, j2))) => intersection_table.table.min_in_rect(i1, j1, i2, j2),
+            // No intersection's x or y coordinate falls in range, so there is nothing to find.
+            _ => u64::MAX,
+        }
+    }
+}
+
 fn main() {
     let line_a = vec!["R1009","U286","L371","U985","R372","D887","R311","U609","L180","D986","L901","D592","R298","U955","R681","D68","R453","U654","L898","U498","R365","D863","L974","U333","L267","D230","R706","D67","L814","D280","R931","D539","R217","U384","L314","D162","L280","U484","L915","D512","L974","D220","R292","U465","L976","U837","R28","U68","L98","D177","L780","U732","R696","D412","L715","U993","L617","U999","R304","D277","R889","D604","R199","U498","R302","U958","R443","U957","R453","U362","R704","U301","R813","U404","L150","D673","L407","D233","L901","D965","R602","U615","R496","U467","R849","U530","L205","D43","R709","U127","L35","U801","L565","D890","R90","D763","R95","D542","R84","D421","L298","D58","R794","U722","R205","U830","L149","D759","L950","D708","L727","U401","L187","D598","L390","D469","R375","U985","L723","U63","L983","D39","L160","U276","R822","D504","L298","D484","L425","U228","L984","D623","L936","U624","L851","D748","L266","D576","L898","U783","L374","D276","R757","U89","L649","U73","L447","D11","L539","U291","L507","U208","R167","D874","L596","D235","R334","U328","R41","D212","L544","D72","L972","D790","L282","U662","R452","U892","L830","D86","L252","U701","L215","U179","L480","U963","L897","U489","R223","U757","R804","U373","R844","D518","R145","U304","L24","D988","R605","D644","R415","U34","L889","D827","R854","U836","R837","D334","L664","D883","L900","U448","R152","U473","R243","D147","L711","U642","R757","U272","R192","U741","L522","U785","L872","D128","L161","D347","L967","D295","R831","U535","R329","D752","R720","D806","R897","D320","R391","D737","L719","U652","L54","D271","L855","D112","R382","U959","R909","D687","L699","U892","L96","D537","L365","D182","R886","U566","R929","U532","L255","U823","R833","U542","R234","D339","R409","U100","L466","U572","L162","U843","L635","D153","L704","D317","L534","U205","R611","D672","L462","D506","L243","U509","L819","D787","R448","D353","R162","U108","R850","D919","R259","U877","R50","D733","L875","U106","L890","D275","L904","U849","L855","U314","L291","U170","L627","U608","R783","U404","R294"];
     let line_b = vec!["L1010","D347","R554","U465","L30","D816","R891","D778","R184","U253","R694","U346","L743","D298","L956","U703","R528","D16","L404","D818","L640","D50","R534","D99","L555","U974","L779","D774","L690","U19","R973","D588","L631","U35","L410","D332","L74","D858","R213","U889","R977","U803","L624","U627","R601","U499","L213","U692","L234","U401","L894","U733","R414","D431","R712","D284","R965","D624","R848","D17","R86","D285","R502","U516","L709","U343","L558","D615","L150","D590","R113","D887","R469","U584","L434","D9","L994","D704","R740","D541","R95","U219","L634","D184","R714","U81","L426","D437","R927","U232","L361","D756","R685","D206","R116","U844","R807","U811","L382","D338","L660","D997","L551","D294","L895","D208","R37","D90","R44","D131","R77","U883","R449","D24","R441","U659","R826","U259","R98","D548","R118","D470","L259","U170","R518","U731","L287","U191","L45","D672","L691","U117","R156","U308","R230","U112","L938","U644","R911","U110","L1","U162","R943","U433","R98","U610","R428","U231","R35","U590","R554","U612","R191","U261","R793","U3","R507","U632","L571","D535","R30","U281","L613","U199","R168","D948","R486","U913","R534","U131","R974","U399","L525","D174","L595","D567","L394","D969","L779","U346","L969","D943","L845","D727","R128","U241","L616","U117","R791","D419","L913","D949","R628","D738","R776","D294","L175","D708","R568","U484","R589","D930","L416","D114","L823","U16","R260","U450","R534","D94","R695","D982","R186","D422","L789","D886","L761","U30","R182","U930","L483","U863","L318","U343","L380","U650","R542","U92","L339","D390","L55","U343","L641","D556","R616","U936","R118","D997","R936","D979","L594","U326","L975","U52","L89","U679","L91","D969","R878","D798","R193","D858","R95","D989","R389","U960","R106","D564","R48","D151","L121","D241","L369","D476","L24","D229","R601","U849","L632","U894","R27","U200","L698","U788","L330","D73","R405","D526","L154","U942","L504","D579","L815","D643","L81","U172","R879","U28","R715","U367","L366","D964","R16","D415","L501","D176","R641","U523","L979","D556","R831"];
-    
+
     enum FieldValues {
         Empty = 0,
         Center = 1,
@@ -277,8 +618,8 @@ fn main() {
     };
 
     let mut wire_field = Field::new(
-        100, 
-        FieldValues::Empty as u8, 
+        100,
+        FieldValues::Empty as u8,
         FieldValues::Center as u8);
 
     wire_field.add_line(&line_a, FieldValues::PointA as u8);
@@ -291,9 +632,9 @@ fn main() {
     }
 
     let mut distances: Vec<i64> = intersections.iter()
-        .map(|cell| (cell.x as i64 - wire_field.center.x as i64).abs() + (cell.y as i64 - wire_field.center.y as i64).abs())
+        .map(|cell| (cell.x as i64).abs() + (cell.y as i64).abs())
         .collect();
-    
+
     for i in 0..intersections.len() {
         println!("Found x = {}, y = {}, distance = {}.", intersections[i].x, intersections[i].y, distances[i]);
     }
@@ -301,10 +642,18 @@ fn main() {
     distances.sort();
     println!("Shortest distance of all intersections: {}.", distances.first().unwrap());
 
-    // Todo remove
-    println!("Center: x={}, y={}", wire_field.center.x, wire_field.center.y);
-
-    let minimum_steps_to_reach_any_intersection = 
+    let minimum_steps_to_reach_any_intersection =
         wire_field.calculate_minimum_steps_to_reach_any_intersection(FieldValues::PointA as u8 | FieldValues::PointB as u8);
     println!("Minimum wire distance to reach an intersection: {}.", minimum_steps_to_reach_any_intersection);
+
+    wire_field.build_intersection_table(FieldValues::PointA as u8 | FieldValues::PointB as u8);
+
+    let x_dim = wire_field.grid.dims[0];
+    let y_dim = wire_field.grid.dims[1];
+    let min_in_whole_field = wire_field.min_in_rect(
+        -(x_dim.offset as i32),
+        -(y_dim.offset as i32),
+        x_dim.size as i32 - x_dim.offset as i32 - 1,
+        y_dim.size as i32 - y_dim.offset as i32 - 1);
+    println!("Minimum combined step cost over the whole field: {}.", min_in_whole_field);
 }